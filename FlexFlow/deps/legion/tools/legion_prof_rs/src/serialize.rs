@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::File;
 use std::io;
 use std::io::Read;
@@ -8,9 +9,10 @@ use flate2::read::GzDecoder;
 
 use nom;
 use nom::{
-    bytes::complete::{tag, take_till, take_while1},
+    bytes::complete::{tag, take, take_till, take_while1},
     character::{is_alphanumeric, is_digit},
-    combinator::{map, map_opt, map_res, opt},
+    combinator::{map, map_res, opt},
+    error::ErrorKind,
     multi::{many1, many_m_n, separated_list1},
     number::complete::{le_i32, le_i64, le_u32, le_u64, le_u8},
     IResult,
@@ -49,6 +51,72 @@ pub enum ValueFormat {
     VariantID,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    InvalidUtf8,
+    IntegerOverflow,
+    UnexpectedByte,
+    UnknownValueFormat(String),
+    UnknownRecordId(u32),
+    Truncated,
+    Nom(ErrorKind),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            ParseErrorKind::IntegerOverflow => write!(f, "integer overflow"),
+            ParseErrorKind::UnexpectedByte => write!(f, "unexpected byte"),
+            ParseErrorKind::UnknownValueFormat(name) => write!(f, "unknown ValueFormat `{}`", name),
+            ParseErrorKind::UnknownRecordId(id) => write!(f, "unknown record id {}", id),
+            ParseErrorKind::Truncated => write!(f, "truncated input"),
+            ParseErrorKind::Nom(kind) => write!(f, "{:?}", kind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub remaining: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(input: &[u8], kind: ParseErrorKind) -> Self {
+        ParseError {
+            remaining: input.len(),
+            kind,
+        }
+    }
+
+    pub fn offset(&self, total_len: usize) -> usize {
+        total_len - self.remaining
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl<'a> nom::error::ParseError<&'a [u8]> for ParseError {
+    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+        ParseError::new(input, ParseErrorKind::Nom(kind))
+    }
+
+    fn append(_input: &'a [u8], _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<'a> nom::error::FromExternalError<&'a [u8], std::string::FromUtf8Error> for ParseError {
+    fn from_external_error(input: &'a [u8], _kind: ErrorKind, _e: std::string::FromUtf8Error) -> Self {
+        ParseError::new(input, ParseErrorKind::InvalidUtf8)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FieldFormat {
     pub name: String,
@@ -63,6 +131,40 @@ pub struct RecordFormat {
     pub fields: Vec<FieldFormat>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub enum Value {
+    Array(Array),
+    Bool(bool),
+    DepPartOpKind(DepPartOpKind),
+    IDType(u64),
+    InstID(InstID),
+    MappingCallKind(MapperCallKindID),
+    MaxDim(MaxDim),
+    MemID(MemID),
+    MemKind(MemKind),
+    MessageKind(i32),
+    Point(Point),
+    ProcID(ProcID),
+    ProcKind(ProcKind),
+    RuntimeCallKind(RuntimeCallKindID),
+    String(String),
+    TaskID(TaskID),
+    Timestamp(Timestamp),
+    U32(u32),
+    U64(u64),
+    I64(i64),
+    UniqueID(UniqueID),
+    VariantID(VariantID),
+    Raw(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedRecord {
+    pub id: u32,
+    pub name: String,
+    pub fields: Vec<(String, Value)>,
+}
+
 // Note: we use different, more specialized types for some of the ones
 // below.
 type DepPartOpKind = i32;
@@ -126,6 +228,9 @@ pub enum Record {
     MapperCallInfo { kind: MapperCallKindID, op_id: OpID, start: Timestamp, stop: Timestamp, proc_id: ProcID, fevent: EventID },
     RuntimeCallInfo { kind: RuntimeCallKindID, start: Timestamp, stop: Timestamp, proc_id: ProcID, fevent: EventID },
     ProfTaskInfo { proc_id: ProcID, op_id: OpID, start: Timestamp, stop: Timestamp, fevent: EventID  },
+    // Fallback for record ids with no hardcoded parser above, decoded
+    // generically from the header's declared `RecordFormat`.
+    Unknown(DecodedRecord),
 }
 
 fn convert_value_format(name: String) -> Option<ValueFormat> {
@@ -160,28 +265,30 @@ fn convert_value_format(name: String) -> Option<ValueFormat> {
 /// Text parser utilities
 ///
 
-fn newline(input: &[u8]) -> IResult<&[u8], ()> {
+fn newline(input: &[u8]) -> IResult<&[u8], (), ParseError> {
     let (input, _) = tag("\n")(input)?;
     Ok((input, ()))
 }
 
-fn parse_text_i32(input: &[u8]) -> IResult<&[u8], i32> {
+fn parse_text_i32(input: &[u8]) -> IResult<&[u8], i32, ParseError> {
     let (input, sign) = opt(tag("-"))(input)?;
-    let (input, value) = take_while1(is_digit)(input)?;
-    let value: i32 = String::from_utf8(value.to_owned())
-        .unwrap()
+    let (rest, digits) = take_while1(is_digit)(input)?;
+    let text = String::from_utf8(digits.to_owned())
+        .map_err(|_| nom::Err::Failure(ParseError::new(input, ParseErrorKind::InvalidUtf8)))?;
+    let value: i32 = text
         .parse()
-        .unwrap();
-    Ok((input, if sign.is_none() { value } else { -value }))
+        .map_err(|_| nom::Err::Failure(ParseError::new(input, ParseErrorKind::IntegerOverflow)))?;
+    Ok((rest, if sign.is_none() { value } else { -value }))
 }
 
-fn parse_text_u32(input: &[u8]) -> IResult<&[u8], u32> {
-    let (input, value) = take_while1(is_digit)(input)?;
-    let value = String::from_utf8(value.to_owned())
-        .unwrap()
+fn parse_text_u32(input: &[u8]) -> IResult<&[u8], u32, ParseError> {
+    let (rest, digits) = take_while1(is_digit)(input)?;
+    let text = String::from_utf8(digits.to_owned())
+        .map_err(|_| nom::Err::Failure(ParseError::new(input, ParseErrorKind::InvalidUtf8)))?;
+    let value: u32 = text
         .parse()
-        .unwrap();
-    Ok((input, value))
+        .map_err(|_| nom::Err::Failure(ParseError::new(input, ParseErrorKind::IntegerOverflow)))?;
+    Ok((rest, value))
 }
 
 #[inline]
@@ -199,21 +306,23 @@ pub fn is_nul(chr: u8) -> bool {
     chr == 0 // nul
 }
 
-fn parse_text_name(input: &[u8]) -> IResult<&[u8], String> {
-    let (input, name) = take_while1(is_alphanumeric_underscore)(input)?;
-    Ok((input, String::from_utf8(name.to_owned()).unwrap()))
+fn parse_text_name(input: &[u8]) -> IResult<&[u8], String, ParseError> {
+    map_res(take_while1(is_alphanumeric_underscore), |name: &[u8]| {
+        String::from_utf8(name.to_owned())
+    })(input)
 }
 
-fn parse_text_type(input: &[u8]) -> IResult<&[u8], String> {
-    let (input, name) = take_while1(is_alphanumeric_space)(input)?;
-    Ok((input, String::from_utf8(name.to_owned()).unwrap()))
+fn parse_text_type(input: &[u8]) -> IResult<&[u8], String, ParseError> {
+    map_res(take_while1(is_alphanumeric_space), |name: &[u8]| {
+        String::from_utf8(name.to_owned())
+    })(input)
 }
 
 ///
 /// Text parsers for the log file header
 ///
 
-fn parse_filetype(input: &[u8]) -> IResult<&[u8], (u32, u32)> {
+fn parse_filetype(input: &[u8]) -> IResult<&[u8], (u32, u32), ParseError> {
     let (input, _) = tag("FileType: BinaryLegionProf v: ")(input)?;
     let (input, version_major) = parse_text_u32(input)?;
     let (input, _) = tag(".")(input)?;
@@ -222,11 +331,18 @@ fn parse_filetype(input: &[u8]) -> IResult<&[u8], (u32, u32)> {
     Ok((input, (version_major, version_minor)))
 }
 
-fn parse_value_format(input: &[u8]) -> IResult<&[u8], ValueFormat> {
-    map_opt(parse_text_type, convert_value_format)(input)
+fn parse_value_format(input: &[u8]) -> IResult<&[u8], ValueFormat, ParseError> {
+    let (rest, name) = parse_text_type(input)?;
+    match convert_value_format(name.clone()) {
+        Some(value) => Ok((rest, value)),
+        None => Err(nom::Err::Failure(ParseError::new(
+            input,
+            ParseErrorKind::UnknownValueFormat(name),
+        ))),
+    }
 }
 
-fn parse_field_format(input: &[u8]) -> IResult<&[u8], FieldFormat> {
+fn parse_field_format(input: &[u8]) -> IResult<&[u8], FieldFormat, ParseError> {
     let (input, name) = parse_text_name(input)?;
     let (input, _) = tag(":")(input)?;
     let (input, value) = parse_value_format(input)?;
@@ -235,7 +351,7 @@ fn parse_field_format(input: &[u8]) -> IResult<&[u8], FieldFormat> {
     Ok((input, FieldFormat { name, value, size }))
 }
 
-fn parse_record_format(input: &[u8]) -> IResult<&[u8], RecordFormat> {
+fn parse_record_format(input: &[u8]) -> IResult<&[u8], RecordFormat, ParseError> {
     let (input, name) = parse_text_name(input)?;
     let (input, _) = tag(" {id:")(input)?;
     let (input, id) = parse_text_u32(input)?;
@@ -250,98 +366,199 @@ fn parse_record_format(input: &[u8]) -> IResult<&[u8], RecordFormat> {
 /// Binary parsers for basic types used in records
 ///
 
-fn parse_array(input: &[u8], max_dim: i32) -> IResult<&[u8], Array> {
-    assert!(max_dim > -1);
+fn parse_array(input: &[u8], max_dim: i32) -> IResult<&[u8], Array, ParseError> {
+    if max_dim <= -1 {
+        return Err(nom::Err::Failure(ParseError::new(
+            input,
+            ParseErrorKind::Nom(ErrorKind::Verify),
+        )));
+    }
     let n = (max_dim * 2) as usize;
     let (input, values) = many_m_n(n, n, le_u64)(input)?;
     Ok((input, Array(values)))
 }
-fn parse_bool(input: &[u8]) -> IResult<&[u8], bool> {
+fn parse_bool(input: &[u8]) -> IResult<&[u8], bool, ParseError> {
     map(le_u8, |x| x != 0)(input)
 }
-fn parse_point(input: &[u8], max_dim: i32) -> IResult<&[u8], Point> {
-    assert!(max_dim > -1);
+fn parse_point(input: &[u8], max_dim: i32) -> IResult<&[u8], Point, ParseError> {
+    if max_dim <= -1 {
+        return Err(nom::Err::Failure(ParseError::new(
+            input,
+            ParseErrorKind::Nom(ErrorKind::Verify),
+        )));
+    }
     let n = max_dim as usize;
     let (input, values) = many_m_n(n, n, le_u64)(input)?;
     Ok((input, Point(values)))
 }
-fn parse_string(input: &[u8]) -> IResult<&[u8], String> {
-    let (input, value) = map_res(take_till(is_nul), |x: &[u8]| {
-        String::from_utf8(x.to_owned())
-    })(input)?;
-    let (input, terminator) = le_u8(input)?;
-    assert!(is_nul(terminator));
-    Ok((input, value))
+fn parse_string(input: &[u8]) -> IResult<&[u8], String, ParseError> {
+    let (rest, bytes) = take_till(is_nul)(input)?;
+    let value = String::from_utf8(bytes.to_owned())
+        .map_err(|_| nom::Err::Failure(ParseError::new(input, ParseErrorKind::InvalidUtf8)))?;
+    let (rest, terminator) = le_u8(rest)?;
+    if !is_nul(terminator) {
+        return Err(nom::Err::Failure(ParseError::new(
+            input,
+            ParseErrorKind::UnexpectedByte,
+        )));
+    }
+    Ok((rest, value))
 }
 
 ///
 /// Binary parsers for type aliases
 ///
 
-fn parse_event_id(input: &[u8]) -> IResult<&[u8], EventID> {
+fn parse_event_id(input: &[u8]) -> IResult<&[u8], EventID, ParseError> {
     map(le_u64, EventID)(input)
 }
-fn parse_inst_uid(input: &[u8]) -> IResult<&[u8], InstUID> {
+fn parse_inst_uid(input: &[u8]) -> IResult<&[u8], InstUID, ParseError> {
     map(le_u64, InstUID)(input)
 }
-fn parse_inst_id(input: &[u8]) -> IResult<&[u8], InstID> {
+fn parse_inst_id(input: &[u8]) -> IResult<&[u8], InstID, ParseError> {
     map(le_u64, InstID)(input)
 }
-fn parse_ipart_id(input: &[u8]) -> IResult<&[u8], IPartID> {
+fn parse_ipart_id(input: &[u8]) -> IResult<&[u8], IPartID, ParseError> {
     map(le_u64, IPartID)(input)
 }
-fn parse_ispace_id(input: &[u8]) -> IResult<&[u8], ISpaceID> {
+fn parse_ispace_id(input: &[u8]) -> IResult<&[u8], ISpaceID, ParseError> {
     map(le_u64, ISpaceID)(input)
 }
-fn parse_fspace_id(input: &[u8]) -> IResult<&[u8], FSpaceID> {
+fn parse_fspace_id(input: &[u8]) -> IResult<&[u8], FSpaceID, ParseError> {
     map(le_u64, FSpaceID)(input)
 }
-fn parse_field_id(input: &[u8]) -> IResult<&[u8], FieldID> {
+fn parse_field_id(input: &[u8]) -> IResult<&[u8], FieldID, ParseError> {
     map(le_u32, FieldID)(input)
 }
-fn parse_tree_id(input: &[u8]) -> IResult<&[u8], TreeID> {
+fn parse_tree_id(input: &[u8]) -> IResult<&[u8], TreeID, ParseError> {
     map(le_u32, TreeID)(input)
 }
-fn parse_mapper_call_kind_id(input: &[u8]) -> IResult<&[u8], MapperCallKindID> {
+fn parse_mapper_call_kind_id(input: &[u8]) -> IResult<&[u8], MapperCallKindID, ParseError> {
     map(le_u32, MapperCallKindID)(input)
 }
-fn parse_mem_id(input: &[u8]) -> IResult<&[u8], MemID> {
+fn parse_mem_id(input: &[u8]) -> IResult<&[u8], MemID, ParseError> {
     map(le_u64, MemID)(input)
 }
-fn parse_op_id(input: &[u8]) -> IResult<&[u8], OpID> {
+fn parse_op_id(input: &[u8]) -> IResult<&[u8], OpID, ParseError> {
     map(le_u64, OpID)(input)
 }
-fn parse_proc_id(input: &[u8]) -> IResult<&[u8], ProcID> {
+fn parse_proc_id(input: &[u8]) -> IResult<&[u8], ProcID, ParseError> {
     map(le_u64, ProcID)(input)
 }
-fn parse_runtime_call_kind_id(input: &[u8]) -> IResult<&[u8], RuntimeCallKindID> {
+fn parse_runtime_call_kind_id(input: &[u8]) -> IResult<&[u8], RuntimeCallKindID, ParseError> {
     map(le_u32, RuntimeCallKindID)(input)
 }
-fn parse_task_id(input: &[u8]) -> IResult<&[u8], TaskID> {
+fn parse_task_id(input: &[u8]) -> IResult<&[u8], TaskID, ParseError> {
     map(le_u32, TaskID)(input)
 }
-fn parse_timestamp(input: &[u8]) -> IResult<&[u8], Timestamp> {
+fn parse_timestamp(input: &[u8]) -> IResult<&[u8], Timestamp, ParseError> {
     map(le_u64, Timestamp)(input)
 }
-fn parse_variant_id(input: &[u8]) -> IResult<&[u8], VariantID> {
+fn parse_variant_id(input: &[u8]) -> IResult<&[u8], VariantID, ParseError> {
     map(le_u32, VariantID)(input)
 }
 
+///
+/// Generic, table-driven record decoder
+///
+
+fn parse_value(input: &[u8], value: ValueFormat, max_dim: i32) -> IResult<&[u8], Value, ParseError> {
+    match value {
+        ValueFormat::Array => map(|i| parse_array(i, max_dim), Value::Array)(input),
+        ValueFormat::Bool => map(parse_bool, Value::Bool)(input),
+        ValueFormat::DepPartOpKind => map(le_i32, Value::DepPartOpKind)(input),
+        ValueFormat::IDType => map(le_u64, Value::IDType)(input),
+        ValueFormat::InstID => map(parse_inst_id, Value::InstID)(input),
+        ValueFormat::MappingCallKind => map(parse_mapper_call_kind_id, Value::MappingCallKind)(input),
+        ValueFormat::MaxDim => map(le_i32, Value::MaxDim)(input),
+        ValueFormat::MemID => map(parse_mem_id, Value::MemID)(input),
+        ValueFormat::MemKind => map(le_i32, Value::MemKind)(input),
+        ValueFormat::MessageKind => map(le_i32, Value::MessageKind)(input),
+        ValueFormat::Point => map(|i| parse_point(i, max_dim), Value::Point)(input),
+        ValueFormat::ProcID => map(parse_proc_id, Value::ProcID)(input),
+        ValueFormat::ProcKind => map(le_i32, Value::ProcKind)(input),
+        ValueFormat::RuntimeCallKind => map(parse_runtime_call_kind_id, Value::RuntimeCallKind)(input),
+        ValueFormat::String => map(parse_string, Value::String)(input),
+        ValueFormat::TaskID => map(parse_task_id, Value::TaskID)(input),
+        ValueFormat::Timestamp => map(parse_timestamp, Value::Timestamp)(input),
+        ValueFormat::U32 => map(le_u32, Value::U32)(input),
+        ValueFormat::U64 => map(le_u64, Value::U64)(input),
+        ValueFormat::I64 => map(le_i64, Value::I64)(input),
+        ValueFormat::UniqueID => map(le_u64, Value::UniqueID)(input),
+        ValueFormat::VariantID => map(parse_variant_id, Value::VariantID)(input),
+    }
+}
+
+// A negative declared size means variable length; trust parse_value's
+// own consumption instead of checking it against a fixed size.
+fn parse_field<'a>(input: &'a [u8], field: &FieldFormat, max_dim: i32) -> IResult<&'a [u8], Value, ParseError> {
+    if field.size < 0 {
+        return parse_value(input, field.value, max_dim);
+    }
+    let size = field.size as usize;
+    match parse_value(input, field.value, max_dim) {
+        Ok((rest, value)) if input.len() - rest.len() == size => Ok((rest, value)),
+        _ if input.len() < size => Err(nom::Err::Failure(ParseError::new(
+            input,
+            ParseErrorKind::Truncated,
+        ))),
+        _ => map(take(size), |raw: &[u8]| Value::Raw(raw.to_owned()))(input),
+    }
+}
+
+fn decode_record_generic<'a>(
+    input: &'a [u8],
+    format: &RecordFormat,
+    max_dim: i32,
+) -> IResult<&'a [u8], DecodedRecord, ParseError> {
+    let mut input = input;
+    let mut fields = Vec::with_capacity(format.fields.len());
+    for field in &format.fields {
+        let (input_, value) = parse_field(input, field, max_dim)?;
+        input = input_;
+        fields.push((field.name.clone(), value));
+    }
+    Ok((
+        input,
+        DecodedRecord {
+            id: format.id,
+            name: format.name.clone(),
+            fields,
+        },
+    ))
+}
+
+// None if any field has a negative (variable) declared size.
+fn record_payload_len(format: &RecordFormat) -> Option<usize> {
+    format.fields.iter().map(|f| f.size).try_fold(0usize, |acc, size| {
+        if size < 0 {
+            None
+        } else {
+            Some(acc + size as usize)
+        }
+    })
+}
+
+fn peek_record_id(input: &[u8]) -> Option<u32> {
+    let bytes: [u8; 4] = input.get(0..4)?.try_into().ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
 ///
 /// Binary parsers for records
 ///
 
-fn parse_mapper_call_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_mapper_call_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, kind) = parse_mapper_call_kind_id(input)?;
     let (input, name) = parse_string(input)?;
     Ok((input, Record::MapperCallDesc { kind, name }))
 }
-fn parse_runtime_call_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_runtime_call_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, kind) = parse_runtime_call_kind_id(input)?;
     let (input, name) = parse_string(input)?;
     Ok((input, Record::RuntimeCallDesc { kind, name }))
 }
-fn parse_meta_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_meta_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, kind) = parse_variant_id(input)?;
     let (input, message) = parse_bool(input)?;
     let (input, ordered_vc) = parse_bool(input)?;
@@ -356,31 +573,31 @@ fn parse_meta_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_op_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_op_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, kind) = le_u32(input)?;
     let (input, name) = parse_string(input)?;
     Ok((input, Record::OpDesc { kind, name }))
 }
-fn parse_max_dim_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_max_dim_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, max_dim) = le_i32(input)?;
     Ok((input, Record::MaxDimDesc { max_dim }))
 }
-fn parse_machine_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_machine_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, nodeid) = le_u32(input)?;
     let (input, num_nodes) = le_u32(input)?;
     let node_id = NodeID(u64::from(nodeid));
     Ok((input, Record::MachineDesc { node_id, num_nodes }))
 }
-fn parse_zero_time(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_zero_time(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, zero_time) = le_i64(input)?;
     Ok((input, Record::ZeroTime { zero_time }))
 }
-fn parse_proc_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_proc_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, proc_id) = parse_proc_id(input)?;
     let (input, kind) = le_i32(input)?;
     Ok((input, Record::ProcDesc { proc_id, kind }))
 }
-fn parse_mem_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_mem_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, mem_id) = parse_mem_id(input)?;
     let (input, kind) = le_i32(input)?;
     let (input, capacity) = le_u64(input)?;
@@ -393,7 +610,7 @@ fn parse_mem_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_mem_proc_affinity_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_mem_proc_affinity_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, proc_id) = parse_proc_id(input)?;
     let (input, mem_id) = parse_mem_id(input)?;
     let (input, bandwidth) = le_u32(input)?;
@@ -408,7 +625,7 @@ fn parse_mem_proc_affinity_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], R
         },
     ))
 }
-fn parse_index_space_point_desc(input: &[u8], max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_index_space_point_desc(input: &[u8], max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, ispace_id) = parse_ispace_id(input)?;
     let (input, dim) = le_u32(input)?;
     let (input, rem) = parse_point(input, max_dim)?;
@@ -421,7 +638,7 @@ fn parse_index_space_point_desc(input: &[u8], max_dim: i32) -> IResult<&[u8], Re
         },
     ))
 }
-fn parse_index_space_rect_desc(input: &[u8], max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_index_space_rect_desc(input: &[u8], max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, ispace_id) = parse_ispace_id(input)?;
     let (input, dim) = le_u32(input)?;
     let (input, rem) = parse_array(input, max_dim)?;
@@ -434,11 +651,11 @@ fn parse_index_space_rect_desc(input: &[u8], max_dim: i32) -> IResult<&[u8], Rec
         },
     ))
 }
-fn parse_index_space_empty_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_index_space_empty_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, ispace_id) = parse_ispace_id(input)?;
     Ok((input, Record::IndexSpaceEmptyDesc { ispace_id }))
 }
-fn parse_field_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_field_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, fspace_id) = parse_fspace_id(input)?;
     let (input, field_id) = parse_field_id(input)?;
     let (input, size) = le_u64(input)?;
@@ -453,22 +670,22 @@ fn parse_field_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_field_space_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_field_space_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, fspace_id) = parse_fspace_id(input)?;
     let (input, name) = parse_string(input)?;
     Ok((input, Record::FieldSpaceDesc { fspace_id, name }))
 }
-fn parse_part_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_part_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, unique_id) = parse_ipart_id(input)?;
     let (input, name) = parse_string(input)?;
     Ok((input, Record::PartDesc { unique_id, name }))
 }
-fn parse_index_space_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_index_space_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, ispace_id) = parse_ispace_id(input)?;
     let (input, name) = parse_string(input)?;
     Ok((input, Record::IndexSpaceDesc { ispace_id, name }))
 }
-fn parse_index_subspace_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_index_subspace_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, parent_id) = parse_ipart_id(input)?;
     let (input, ispace_id) = parse_ispace_id(input)?;
     Ok((
@@ -479,7 +696,7 @@ fn parse_index_subspace_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Reco
         },
     ))
 }
-fn parse_index_partition_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_index_partition_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, parent_id) = parse_ispace_id(input)?;
     let (input, unique_id) = parse_ipart_id(input)?;
     let (input, disjoint) = parse_bool(input)?;
@@ -494,7 +711,7 @@ fn parse_index_partition_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Rec
         },
     ))
 }
-fn parse_index_space_size_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_index_space_size_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, ispace_id) = parse_ispace_id(input)?;
     let (input, dense_size) = le_u64(input)?;
     let (input, sparse_size) = le_u64(input)?;
@@ -509,7 +726,7 @@ fn parse_index_space_size_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Re
         },
     ))
 }
-fn parse_logical_region_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_logical_region_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, ispace_id) = parse_ispace_id(input)?;
     let (input, fspace_id) = le_u32(input)?;
     let (input, tree_id) = parse_tree_id(input)?;
@@ -524,7 +741,7 @@ fn parse_logical_region_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Reco
         },
     ))
 }
-fn parse_physical_inst_region_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_physical_inst_region_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, inst_uid) = parse_inst_uid(input)?;
     let (input, ispace_id) = parse_ispace_id(input)?;
     let (input, fspace_id) = le_u32(input)?;
@@ -539,7 +756,7 @@ fn parse_physical_inst_region_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8]
         },
     ))
 }
-fn parse_physical_inst_layout_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_physical_inst_layout_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, inst_uid) = parse_inst_uid(input)?;
     let (input, field_id) = parse_field_id(input)?;
     let (input, fspace_id) = le_u32(input)?;
@@ -558,7 +775,7 @@ fn parse_physical_inst_layout_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8]
         },
     ))
 }
-fn parse_physical_inst_layout_dim_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_physical_inst_layout_dim_desc(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, inst_uid) = parse_inst_uid(input)?;
     let (input, dim) = le_u32(input)?;
     let (input, dim_kind) = le_u32(input)?;
@@ -571,7 +788,7 @@ fn parse_physical_inst_layout_dim_desc(input: &[u8], _max_dim: i32) -> IResult<&
         },
     ))
 }
-fn parse_physical_inst_usage(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_physical_inst_usage(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, inst_uid) = parse_inst_uid(input)?;
     let (input, op_id) = parse_op_id(input)?;
     let (input, index_id) = le_u32(input)?;
@@ -586,7 +803,7 @@ fn parse_physical_inst_usage(input: &[u8], _max_dim: i32) -> IResult<&[u8], Reco
         },
     ))
 }
-fn parse_task_kind(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_task_kind(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, task_id) = parse_task_id(input)?;
     let (input, name) = parse_string(input)?;
     let (input, overwrite) = parse_bool(input)?;
@@ -599,7 +816,7 @@ fn parse_task_kind(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_task_variant(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_task_variant(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, task_id) = parse_task_id(input)?;
     let (input, variant_id) = parse_variant_id(input)?;
     let (input, name) = parse_string(input)?;
@@ -612,7 +829,7 @@ fn parse_task_variant(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_operation(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_operation(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, parent_id) = parse_op_id(input)?;
     let (input, kind) = le_u32(input)?;
@@ -627,17 +844,17 @@ fn parse_operation(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_multi_task(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_multi_task(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, task_id) = parse_task_id(input)?;
     Ok((input, Record::MultiTask { op_id, task_id }))
 }
-fn parse_slice_owner(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_slice_owner(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, parent_id) = le_u64(input)?;
     let (input, op_id) = parse_op_id(input)?;
     Ok((input, Record::SliceOwner { parent_id, op_id }))
 }
-fn parse_task_wait_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_task_wait_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, task_id) = parse_task_id(input)?;
     let (input, variant_id) = parse_variant_id(input)?;
@@ -656,7 +873,7 @@ fn parse_task_wait_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_meta_wait_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_meta_wait_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, lg_id) = parse_variant_id(input)?;
     let (input, wait_start) = parse_timestamp(input)?;
@@ -673,7 +890,7 @@ fn parse_meta_wait_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_task_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_task_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, task_id) = parse_task_id(input)?;
     let (input, variant_id) = parse_variant_id(input)?;
@@ -698,7 +915,7 @@ fn parse_task_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_gpu_task_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_gpu_task_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, task_id) = parse_task_id(input)?;
     let (input, variant_id) = parse_variant_id(input)?;
@@ -727,7 +944,7 @@ fn parse_gpu_task_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_meta_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_meta_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, lg_id) = parse_variant_id(input)?;
     let (input, proc_id) = parse_proc_id(input)?;
@@ -750,7 +967,7 @@ fn parse_meta_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_copy_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_copy_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, size) = le_u64(input)?;
     let (input, create) = parse_timestamp(input)?;
@@ -773,7 +990,7 @@ fn parse_copy_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_copy_inst_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_copy_inst_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, src) = parse_mem_id(input)?;
     let (input, dst) = parse_mem_id(input)?;
     let (input, src_fid) = parse_field_id(input)?;
@@ -798,7 +1015,7 @@ fn parse_copy_inst_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_fill_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_fill_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, size) = le_u64(input)?;
     let (input, create) = parse_timestamp(input)?;
@@ -819,7 +1036,7 @@ fn parse_fill_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_fill_inst_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_fill_inst_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, dst) = parse_mem_id(input)?;
     let (input, fid) = parse_field_id(input)?;
     let (input, dst_inst) = parse_inst_uid(input)?;
@@ -834,7 +1051,7 @@ fn parse_fill_inst_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_inst_timeline(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_inst_timeline(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, inst_uid) = parse_inst_uid(input)?;
     let (input, inst_id) = parse_inst_id(input)?;
     let (input, mem_id) = parse_mem_id(input)?;
@@ -857,7 +1074,7 @@ fn parse_inst_timeline(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_partition_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_partition_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, op_id) = parse_op_id(input)?;
     let (input, part_op) = le_i32(input)?;
     let (input, create) = parse_timestamp(input)?;
@@ -876,7 +1093,7 @@ fn parse_partition_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
         },
     ))
 }
-fn parse_mapper_call_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_mapper_call_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, kind) = parse_mapper_call_kind_id(input)?;
     let (input, op_id) = parse_op_id(input)?;
     let (input, start) = parse_timestamp(input)?;
@@ -895,7 +1112,7 @@ fn parse_mapper_call_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record>
         },
     ))
 }
-fn parse_runtime_call_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_runtime_call_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, kind) = parse_runtime_call_kind_id(input)?;
     let (input, start) = parse_timestamp(input)?;
     let (input, stop) = parse_timestamp(input)?;
@@ -912,7 +1129,7 @@ fn parse_runtime_call_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record
         },
     ))
 }
-fn parse_proftask_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record> {
+fn parse_proftask_info(input: &[u8], _max_dim: i32) -> IResult<&[u8], Record, ParseError> {
     let (input, proc_id) = parse_proc_id(input)?;
     let (input, op_id) = parse_op_id(input)?;
     let (input, start) = parse_timestamp(input)?;
@@ -976,29 +1193,44 @@ fn filter_record<'a>(
 
 fn parse_record<'a>(
     input: &'a [u8],
-    parsers: &BTreeMap<u32, fn(&[u8], i32) -> IResult<&[u8], Record>>,
+    parsers: &BTreeMap<u32, fn(&[u8], i32) -> IResult<&[u8], Record, ParseError>>,
+    formats: &BTreeMap<u32, RecordFormat>,
     max_dim: i32,
-) -> IResult<&'a [u8], Record> {
+) -> IResult<&'a [u8], Record, ParseError> {
     let (input, id) = le_u32(input)?;
-    let parser = &parsers[&id];
-    parser(input, max_dim)
+    match parsers.get(&id) {
+        Some(parser) => parser(input, max_dim),
+        // No hardcoded parser for this id: the header still told us its
+        // shape, so decode it generically rather than aborting the parse.
+        None => {
+            let format = formats.get(&id).ok_or_else(|| {
+                nom::Err::Failure(ParseError::new(input, ParseErrorKind::UnknownRecordId(id)))
+            })?;
+            let (input, decoded) = decode_record_generic(input, format, max_dim)?;
+            Ok((input, Record::Unknown(decoded)))
+        }
+    }
 }
 
-fn parse<'a>(
-    input: &'a [u8],
-    visible_nodes: &'a Vec<NodeID>,
-    filter_input: bool,
-) -> IResult<&'a [u8], Vec<Record>> {
+fn parse_header(
+    input: &[u8],
+) -> IResult<&[u8], ((u32, u32), BTreeMap<String, u32>, BTreeMap<u32, RecordFormat>), ParseError> {
     let (input, version) = parse_filetype(input)?;
-    assert_eq!(version, (1, 0));
     let (input, record_formats) = many1(parse_record_format)(input)?;
     let mut ids = BTreeMap::new();
+    let mut formats = BTreeMap::new();
     for record_format in record_formats {
-        ids.insert(record_format.name, record_format.id);
+        ids.insert(record_format.name.clone(), record_format.id);
+        formats.insert(record_format.id, record_format);
     }
     let (input, _) = newline(input)?;
+    Ok((input, (version, ids, formats)))
+}
 
-    let mut parsers = BTreeMap::<u32, fn(&[u8], i32) -> IResult<&[u8], Record>>::new();
+fn known_parsers(
+    ids: &BTreeMap<String, u32>,
+) -> BTreeMap<u32, fn(&[u8], i32) -> IResult<&[u8], Record, ParseError>> {
+    let mut parsers = BTreeMap::<u32, fn(&[u8], i32) -> IResult<&[u8], Record, ParseError>>::new();
     parsers.insert(ids["MapperCallDesc"], parse_mapper_call_desc);
     parsers.insert(ids["RuntimeCallDesc"], parse_runtime_call_desc);
     parsers.insert(ids["MetaDesc"], parse_meta_desc);
@@ -1052,36 +1284,338 @@ fn parse<'a>(
     parsers.insert(ids["MapperCallInfo"], parse_mapper_call_info);
     parsers.insert(ids["RuntimeCallInfo"], parse_runtime_call_info);
     parsers.insert(ids["ProfTaskInfo"], parse_proftask_info);
+    parsers
+}
 
+fn parse<'a>(
+    input: &'a [u8],
+    visible_nodes: &'a Vec<NodeID>,
+    filter_input: bool,
+    lenient: bool,
+) -> IResult<&'a [u8], Vec<Record>, ParseError> {
+    let (input, (version, ids, formats)) = parse_header(input)?;
+    if version != (1, 0) {
+        return Err(nom::Err::Failure(ParseError::new(
+            input,
+            ParseErrorKind::UnexpectedByte,
+        )));
+    }
+    let parsers = known_parsers(&ids);
+
+    let total_len = input.len();
     let mut input = input;
     let mut max_dim = -1;
     let mut node_id: Option<NodeID> = None;
     let mut records = Vec::new();
-    while let Ok((input_, record)) = parse_record(input, &parsers, max_dim) {
-        if let Record::MaxDimDesc { max_dim: d } = &record {
-            max_dim = *d;
-        }
-        if let Record::MachineDesc { node_id: d, .. } = &record {
-            node_id = Some(*d);
-        }
-        input = input_;
-        if !filter_input || filter_record(&record, visible_nodes, node_id) {
-            records.push(record);
+    loop {
+        match parse_record(input, &parsers, &formats, max_dim) {
+            Ok((rest, record)) => {
+                if let Record::MaxDimDesc { max_dim: d } = &record {
+                    max_dim = *d;
+                }
+                if let Record::MachineDesc { node_id: d, .. } = &record {
+                    node_id = Some(*d);
+                }
+                input = rest;
+                if !filter_input || filter_record(&record, visible_nodes, node_id) {
+                    records.push(record);
+                }
+            }
+            Err(e) => {
+                let skip = lenient
+                    .then(|| peek_record_id(input))
+                    .flatten()
+                    .and_then(|id| formats.get(&id).map(|f| (id, f)))
+                    .and_then(|(id, f)| record_payload_len(f).map(|len| (id, 4 + len)));
+                match skip {
+                    Some((id, len)) if len <= input.len() => {
+                        eprintln!(
+                            "legion_prof_rs: skipping malformed record {} at offset {}: {}",
+                            id,
+                            total_len - input.len(),
+                            describe_parse_err(&e),
+                        );
+                        input = &input[len..];
+                    }
+                    _ => break,
+                }
+            }
         }
     }
     Ok((input, records))
 }
 
+fn describe_parse_err(err: &nom::Err<ParseError>) -> String {
+    match err {
+        nom::Err::Incomplete(_) => "not enough data".to_string(),
+        nom::Err::Error(e) | nom::Err::Failure(e) => e.to_string(),
+    }
+}
+
 pub fn deserialize<P: AsRef<Path>>(
     path: P,
     visible_nodes: &Vec<NodeID>,
     filter_input: bool,
+    lenient: bool,
 ) -> io::Result<Vec<Record>> {
     let mut gz = GzDecoder::new(File::open(path)?);
     let mut s = Vec::<u8>::new();
     gz.read_to_end(&mut s)?;
-    // throw error here if parse failed
-    let (rest, records) = parse(&s, visible_nodes, filter_input).unwrap();
-    assert_eq!(rest.len(), 0);
+    let (rest, records) = parse(&s, visible_nodes, filter_input, lenient).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("failed to parse Legion Prof log: {}", describe_parse_err(&e)),
+        )
+    })?;
+    if !rest.is_empty() && !lenient {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "failed to parse Legion Prof log: {} trailing byte(s) after the last decodable record",
+                rest.len()
+            ),
+        ));
+    }
     Ok(records)
 }
+
+const RECORD_READER_CHUNK_SIZE: usize = 64 * 1024;
+
+// Streams records out of a Read (typically a GzDecoder) with bounded
+// memory instead of materializing the whole decompressed log up front.
+pub struct RecordReader<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    parsers: BTreeMap<u32, fn(&[u8], i32) -> IResult<&[u8], Record, ParseError>>,
+    formats: BTreeMap<u32, RecordFormat>,
+    max_dim: i32,
+    at_eof: bool,
+    lenient: bool,
+    consumed: u64,
+}
+
+impl<R: Read> RecordReader<R> {
+    pub fn new(mut inner: R, lenient: bool) -> io::Result<Self> {
+        let mut buf = Vec::new();
+        let (consumed, version, ids, formats) = loop {
+            // Resolve `rest`'s borrow of `buf` into a byte count so the
+            // `Err` arm below is free to borrow `buf` mutably.
+            let parsed = parse_header(&buf).map(|(rest, parsed)| (buf.len() - rest.len(), parsed));
+            match parsed {
+                Ok((consumed, (version, ids, formats))) => break (consumed, version, ids, formats),
+                Err(_) => {
+                    if Self::fill(&mut inner, &mut buf)? == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "truncated or malformed Legion Prof log header",
+                        ));
+                    }
+                }
+            }
+        };
+        if version != (1, 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported Legion Prof log version {:?}", version),
+            ));
+        }
+        buf.drain(0..consumed);
+        Ok(RecordReader {
+            inner,
+            buf,
+            parsers: known_parsers(&ids),
+            formats,
+            max_dim: -1,
+            at_eof: false,
+            lenient,
+            consumed: consumed as u64,
+        })
+    }
+
+    fn fill(inner: &mut R, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let start = buf.len();
+        buf.resize(start + RECORD_READER_CHUNK_SIZE, 0);
+        let n = inner.read(&mut buf[start..])?;
+        buf.truncate(start + n);
+        Ok(n)
+    }
+
+    fn declared_record_len(&self) -> Option<(u32, usize)> {
+        let id = peek_record_id(&self.buf)?;
+        let format = self.formats.get(&id)?;
+        let len = record_payload_len(format)?;
+        Some((id, 4 + len))
+    }
+
+    fn have_full_record(&self) -> bool {
+        matches!(self.declared_record_len(), Some((_, len)) if self.buf.len() >= len)
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match parse_record(&self.buf, &self.parsers, &self.formats, self.max_dim) {
+                Ok((rest, record)) => {
+                    let consumed = self.buf.len() - rest.len();
+                    self.buf.drain(0..consumed);
+                    self.consumed += consumed as u64;
+                    if let Record::MaxDimDesc { max_dim } = &record {
+                        self.max_dim = *max_dim;
+                    }
+                    return Some(Ok(record));
+                }
+                // nom's "complete" parsers report running out of input the
+                // same way as a genuine error, so read ahead and retry first.
+                Err(_) if !self.at_eof && !self.have_full_record() => {
+                    match Self::fill(&mut self.inner, &mut self.buf) {
+                        Ok(0) => self.at_eof = true,
+                        Ok(_) => continue,
+                        Err(e) => return Some(Err(e)),
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    if self.buf.is_empty() {
+                        return None;
+                    }
+                    if self.lenient {
+                        if let Some((id, len)) = self.declared_record_len() {
+                            let skip = len.min(self.buf.len());
+                            eprintln!(
+                                "legion_prof_rs: skipping malformed record {} at offset {}: {}",
+                                id,
+                                self.consumed,
+                                describe_parse_err(&e),
+                            );
+                            self.buf.drain(0..skip);
+                            self.consumed += skip as u64;
+                            continue;
+                        }
+                    }
+                    // Truncated or genuinely malformed; clear buf so the
+                    // next call returns None instead of repeating this error.
+                    self.at_eof = true;
+                    self.buf.clear();
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "malformed record at offset {}: {}",
+                            self.consumed,
+                            describe_parse_err(&e),
+                        ),
+                    )));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_record_generic_consumes_variable_length_field_exactly() {
+        let format = RecordFormat {
+            id: 0,
+            name: "Foo".to_owned(),
+            fields: vec![
+                FieldFormat {
+                    name: "name".to_owned(),
+                    value: ValueFormat::String,
+                    size: -1,
+                },
+                FieldFormat {
+                    name: "count".to_owned(),
+                    value: ValueFormat::U32,
+                    size: 4,
+                },
+            ],
+        };
+        let mut input = b"foo\0".to_vec();
+        input.extend_from_slice(&42u32.to_le_bytes());
+
+        let (rest, decoded) = decode_record_generic(&input, &format, -1).unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(&decoded.fields[0], (name, Value::String(s)) if name == "name" && s == "foo"));
+        assert!(matches!(&decoded.fields[1], (name, Value::U32(42)) if name == "count"));
+    }
+
+    // known_parsers looks up every well-known record name unconditionally,
+    // so the header has to declare all of them even though this test only
+    // cares about MapperCallDesc (given id 0 here).
+    const KNOWN_RECORD_NAMES: &[&str] = &[
+        "MapperCallDesc", "RuntimeCallDesc", "MetaDesc", "OpDesc", "MaxDimDesc",
+        "MachineDesc", "ZeroTime", "ProcDesc", "MemDesc", "ProcMDesc",
+        "IndexSpacePointDesc", "IndexSpaceRectDesc", "IndexSpaceEmptyDesc", "FieldDesc",
+        "FieldSpaceDesc", "PartDesc", "IndexSpaceDesc", "IndexSubSpaceDesc",
+        "IndexPartitionDesc", "IndexSpaceSizeDesc", "LogicalRegionDesc",
+        "PhysicalInstRegionDesc", "PhysicalInstLayoutDesc", "PhysicalInstDimOrderDesc",
+        "PhysicalInstanceUsage", "TaskKind", "TaskVariant", "OperationInstance",
+        "MultiTask", "SliceOwner", "TaskWaitInfo", "MetaWaitInfo", "TaskInfo",
+        "GPUTaskInfo", "MetaInfo", "CopyInfo", "CopyInstInfo", "FillInfo",
+        "FillInstInfo", "InstTimelineInfo", "PartitionInfo", "MapperCallInfo",
+        "RuntimeCallInfo", "ProfTaskInfo",
+    ];
+
+    fn full_header() -> String {
+        let mut header = String::from("FileType: BinaryLegionProf v: 1.0\n");
+        for (id, name) in KNOWN_RECORD_NAMES.iter().enumerate() {
+            if *name == "MapperCallDesc" {
+                header += &format!("{} {{id:{}, kind:unsigned:4, name:string:8}}\n", name, id);
+            } else {
+                header += &format!("{} {{id:{}, f:unsigned:4}}\n", name, id);
+            }
+        }
+        header += "\n";
+        header
+    }
+
+    fn malformed_mapper_call_desc_log() -> Vec<u8> {
+        let mut input = full_header().into_bytes();
+        input.extend_from_slice(&0u32.to_le_bytes()); // MapperCallDesc's id
+        input.extend_from_slice(&0u32.to_le_bytes()); // kind
+        input.extend_from_slice(b"AAAAAAAA"); // name, missing its NUL terminator
+        input
+    }
+
+    #[test]
+    fn parse_non_lenient_stops_at_the_first_malformed_record() {
+        let visible_nodes = Vec::new();
+        let input = malformed_mapper_call_desc_log();
+        let (rest, records) = parse(&input, &visible_nodes, false, false).unwrap();
+        assert!(records.is_empty());
+        assert_eq!(rest.len(), 16); // the whole malformed record, left for the caller to reject
+    }
+
+    #[test]
+    fn parse_lenient_skips_the_malformed_record_by_its_declared_length() {
+        let visible_nodes = Vec::new();
+        let input = malformed_mapper_call_desc_log();
+        let (rest, records) = parse(&input, &visible_nodes, false, true).unwrap();
+        assert!(records.is_empty());
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn parse_stops_at_an_id_the_header_never_declared() {
+        let visible_nodes = Vec::new();
+        let mut input = full_header().into_bytes();
+        input.extend_from_slice(&999u32.to_le_bytes()); // not in parsers or formats
+        input.extend_from_slice(b"junk");
+
+        // Non-lenient: parse stops at the first record it cannot decode and
+        // leaves that record's bytes in rest.
+        let (rest, records) = parse(&input, &visible_nodes, false, false).unwrap();
+        assert!(records.is_empty());
+        assert_eq!(rest.len(), 8);
+
+        // Lenient doesn't change this: there's no declared length to skip by.
+        let (rest, records) = parse(&input, &visible_nodes, false, true).unwrap();
+        assert!(records.is_empty());
+        assert_eq!(rest.len(), 8);
+    }
+}